@@ -1,14 +1,25 @@
-use std::{collections::HashMap, sync::mpsc::Sender, time::Duration};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use chrono::Utc;
 use futures_util::{stream::SplitSink, SinkExt, StreamExt};
-use tokio::{net::TcpStream, select, sync::mpsc::UnboundedReceiver, time};
+use tokio::{net::TcpStream, select, sync::{mpsc::UnboundedReceiver, Notify}, time};
 use tokio_tungstenite::{
     connect_async,
-    tungstenite::{client::IntoClientRequest, Message},
+    tungstenite::{client::IntoClientRequest, Error as WsError, Message},
     MaybeTlsStream,
     WebSocketStream,
 };
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 use crate::{
@@ -35,10 +46,183 @@ use crate::{
     types::{Depth, Error, LiveEvent, OrderResponse, Side, Trade, BUY, SELL},
 };
 
+/// One pending item in an [`EventTx`]/[`EventRx`] pipe. A `Depth` slot only
+/// records which asset it belongs to; the payload itself lives in
+/// `EventPipeState::latest_depth` so a repeated update for the same asset
+/// overwrites it in place instead of growing the queue.
+enum EventSlot {
+    Depth(usize),
+    Other(LiveEvent),
+}
+
+/// Maximum number of non-`Depth` events (`Trade`/`Order`/`Position`/`Error`) the pipe
+/// will buffer, tracked via `EventPipeState::other_count` rather than the raw
+/// queue length so that unconflated `Depth` slots (one per distinct live
+/// asset) never eat into the budget meant for events that must never be
+/// dropped. Once the cap is hit, `EventTx::send` awaits the consumer draining
+/// a slot, which is what actually bounds memory under burst load.
+const EVENT_QUEUE_CAPACITY: usize = 1024;
+
+#[derive(Default)]
+struct EventPipeState {
+    queue: VecDeque<EventSlot>,
+    latest_depth: HashMap<usize, Depth>,
+    other_count: usize,
+}
+
+struct EventPipe {
+    state: Mutex<EventPipeState>,
+    not_empty: Condvar,
+    not_full: Notify,
+    closed: AtomicBool,
+}
+
+/// The sending half of a bounded, QoS-aware event pipe from a Bybit
+/// connector to the live engine.
+///
+/// Order-book state is fully replaced by each update, so when the consumer
+/// falls behind, consecutive `LiveEvent::Depth` updates for the same asset
+/// are conflated down to the latest one instead of piling up unbounded.
+/// `LiveEvent::Order`, `LiveEvent::Position`, and `LiveEvent::Error` are
+/// never conflated or dropped, and are always delivered in send order; once
+/// `EVENT_QUEUE_CAPACITY` non-`Depth` events (these and `LiveEvent::Trade`)
+/// are queued, `send` awaits the consumer
+/// catching up rather than growing the queue further. The wait is a
+/// `tokio::sync::Notify` await, not a blocking one, so a stalled consumer
+/// only suspends the calling task and doesn't park a runtime worker thread
+/// out from under the other connectors sharing it.
+#[derive(Clone)]
+pub struct EventTx {
+    inner: Arc<EventPipe>,
+}
+
+/// The receiving half of an [`EventTx`] pipe.
+pub struct EventRx {
+    inner: Arc<EventPipe>,
+}
+
+/// Creates a linked [`EventTx`]/[`EventRx`] pair.
+pub fn event_channel() -> (EventTx, EventRx) {
+    let inner = Arc::new(EventPipe {
+        state: Mutex::new(EventPipeState::default()),
+        not_empty: Condvar::new(),
+        not_full: Notify::new(),
+        closed: AtomicBool::new(false),
+    });
+    (
+        EventTx {
+            inner: inner.clone(),
+        },
+        EventRx { inner },
+    )
+}
+
+impl EventTx {
+    /// Sends an event, conflating `Depth` updates for the same asset.
+    /// All other events (`Trade`/`Order`/`Position`/`Error`) await the
+    /// consumer draining a slot once `EVENT_QUEUE_CAPACITY` of them are
+    /// already queued. Returns
+    /// `false` if the receiving end has been dropped (including while
+    /// waiting for space); callers should treat that as a signal to shut the
+    /// connector down cleanly rather than panicking.
+    pub async fn send(&self, event: LiveEvent) -> bool {
+        let depth = match event {
+            LiveEvent::Depth(depth) => depth,
+            other => return self.send_other(other).await,
+        };
+        if self.inner.closed.load(AtomicOrdering::Acquire) {
+            return false;
+        }
+        let mut state = self.inner.state.lock().unwrap();
+        let asset_no = depth.asset_no;
+        if state.latest_depth.insert(asset_no, depth).is_none() {
+            state.queue.push_back(EventSlot::Depth(asset_no));
+        }
+        drop(state);
+        self.inner.not_empty.notify_one();
+        true
+    }
+
+    async fn send_other(&self, event: LiveEvent) -> bool {
+        loop {
+            if self.inner.closed.load(AtomicOrdering::Acquire) {
+                return false;
+            }
+            let not_full = self.inner.not_full.notified();
+            let mut state = self.inner.state.lock().unwrap();
+            if state.other_count < EVENT_QUEUE_CAPACITY {
+                state.other_count += 1;
+                state.queue.push_back(EventSlot::Other(event));
+                drop(state);
+                self.inner.not_empty.notify_one();
+                return true;
+            }
+            drop(state);
+            not_full.await;
+        }
+    }
+
+    /// Returns `true` once the receiving end has been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(AtomicOrdering::Acquire)
+    }
+}
+
+impl EventRx {
+    /// Blocks until an event is available, returning `None` once the
+    /// sending end has been dropped and the queue has drained.
+    pub fn recv(&self) -> Option<LiveEvent> {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if let Some(slot) = state.queue.pop_front() {
+                let freed_other_slot = matches!(slot, EventSlot::Other(_));
+                let event = match slot {
+                    EventSlot::Depth(asset_no) => {
+                        LiveEvent::Depth(state.latest_depth.remove(&asset_no).unwrap())
+                    }
+                    EventSlot::Other(event) => {
+                        state.other_count -= 1;
+                        event
+                    }
+                };
+                drop(state);
+                // Only an Other slot actually frees capacity a parked sender is
+                // waiting on; waking on every Depth pop would spuriously bounce
+                // senders parked in `send_other` back to sleep.
+                if freed_other_slot {
+                    self.inner.not_full.notify_one();
+                }
+                return Some(event);
+            }
+            if self.inner.closed.load(AtomicOrdering::Acquire)
+                || Arc::strong_count(&self.inner) == 1
+            {
+                return None;
+            }
+            // Dropping the last `EventTx` sends no wake-up, so wait with a
+            // timeout to notice it.
+            state = self
+                .inner
+                .not_empty
+                .wait_timeout(state, Duration::from_millis(100))
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+impl Drop for EventRx {
+    fn drop(&mut self) {
+        self.inner.closed.store(true, AtomicOrdering::Release);
+        self.inner.not_empty.notify_all();
+        self.inner.not_full.notify_waiters();
+    }
+}
+
 pub struct BybitOrderReq {
     pub op: String,
     pub bybit_order: BybitOrder,
-    pub tx: Sender<LiveEvent>,
+    pub tx: EventTx,
 }
 
 fn parse_depth(
@@ -60,30 +244,221 @@ fn parse_px_qty_tup(px: String, qty: String) -> Result<(f32, f32), HandleError>
     Ok((px.parse()?, qty.parse()?))
 }
 
+/// Jittered exponential backoff used by the reconnection wrappers around
+/// `connect_public`, `connect_private`, and `connect_trade`. The delay resets
+/// back to the initial value once a connection has stayed up long enough to
+/// be considered stable.
+struct Backoff {
+    current: Duration,
+    connected_at: Option<Instant>,
+}
+
+impl Backoff {
+    const INITIAL: Duration = Duration::from_millis(500);
+    const MAX: Duration = Duration::from_secs(30);
+    const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+    fn new() -> Self {
+        Self {
+            current: Self::INITIAL,
+            connected_at: None,
+        }
+    }
+
+    fn mark_attempt_started(&mut self) {
+        self.connected_at = Some(Instant::now());
+    }
+
+    /// Sleeps for the current backoff plus a small jitter, then doubles the
+    /// backoff for next time, capped at `MAX`. If the previous attempt stayed
+    /// connected for at least `STABLE_AFTER`, the backoff is reset first.
+    async fn wait(&mut self) {
+        if let Some(connected_at) = self.connected_at {
+            if connected_at.elapsed() >= Self::STABLE_AFTER {
+                self.current = Self::INITIAL;
+            }
+        }
+        let jitter = Duration::from_millis(Utc::now().timestamp_subsec_millis() as u64 % 250);
+        time::sleep(self.current + jitter).await;
+        self.current = (self.current * 2).min(Self::MAX);
+    }
+}
+
+/// The number of price levels forwarded per side in a merged [`LiveEvent::Depth`].
+const DEPTH_LIMIT: usize = 50;
+
+/// A price used as a `BTreeMap` key. Bybit prices are never `NaN`, so
+/// `f32::total_cmp` gives a total order without the panics a bare
+/// `partial_cmp().unwrap()` would risk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Px(f32);
+
+impl Eq for Px {}
+
+impl PartialOrd for Px {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Px {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// A locally-maintained order book for one symbol, built from Bybit's
+/// snapshot/delta orderbook stream. A `delta` is only valid immediately
+/// after the snapshot or delta carrying `last_u`; any gap means the book is
+/// stale and must be rebuilt from a fresh snapshot.
+#[derive(Default)]
+struct LocalBook {
+    last_u: Option<u64>,
+    bids: BTreeMap<Px, f32>,
+    asks: BTreeMap<Px, f32>,
+}
+
+impl LocalBook {
+    fn apply_snapshot(&mut self, u: u64, bids: &[(f32, f32)], asks: &[(f32, f32)]) {
+        self.bids.clear();
+        self.asks.clear();
+        for &(px, qty) in bids {
+            Self::apply_level(&mut self.bids, px, qty);
+        }
+        for &(px, qty) in asks {
+            Self::apply_level(&mut self.asks, px, qty);
+        }
+        self.last_u = Some(u);
+    }
+
+    /// Applies a delta on top of the last snapshot/delta. Returns `false` if
+    /// `u` doesn't immediately follow `last_u`, signalling a gap: the caller
+    /// must drop this book and resubscribe for a fresh snapshot.
+    fn apply_delta(&mut self, u: u64, bids: &[(f32, f32)], asks: &[(f32, f32)]) -> bool {
+        match self.last_u {
+            Some(last_u) if u == last_u + 1 => {}
+            _ => return false,
+        }
+        for &(px, qty) in bids {
+            Self::apply_level(&mut self.bids, px, qty);
+        }
+        for &(px, qty) in asks {
+            Self::apply_level(&mut self.asks, px, qty);
+        }
+        self.last_u = Some(u);
+        true
+    }
+
+    fn apply_level(side: &mut BTreeMap<Px, f32>, px: f32, qty: f32) {
+        if qty == 0.0 {
+            side.remove(&Px(px));
+        } else {
+            side.insert(Px(px), qty);
+        }
+    }
+
+    fn top_n(&self, n: usize) -> (Vec<(f32, f32)>, Vec<(f32, f32)>) {
+        let bids = self.bids.iter().rev().take(n).map(|(px, &qty)| (px.0, qty)).collect();
+        let asks = self.asks.iter().take(n).map(|(px, &qty)| (px.0, qty)).collect();
+        (bids, asks)
+    }
+}
+
+/// Tracks inbound traffic liveness so a half-open socket that silently stops
+/// responding to pings can be detected and torn down rather than left
+/// pinging into the void forever.
+struct Watchdog {
+    timeout: Duration,
+    last_message_at: Instant,
+    last_pong_at: Option<Instant>,
+}
+
+impl Watchdog {
+    fn new(ping_interval: Duration) -> Self {
+        Self {
+            timeout: ping_interval * 3,
+            last_message_at: Instant::now(),
+            last_pong_at: None,
+        }
+    }
+
+    fn on_message(&mut self) {
+        self.last_message_at = Instant::now();
+    }
+
+    fn on_pong(&mut self) {
+        self.last_pong_at = Some(Instant::now());
+        self.on_message();
+    }
+
+    fn is_stale(&self) -> bool {
+        self.last_message_at.elapsed() >= self.timeout
+    }
+}
+
 async fn handle_public_stream(
     text: &str,
-    ev_tx: &Sender<LiveEvent>,
+    write: &mut SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
+    ev_tx: &EventTx,
     assets: &HashMap<String, Asset>,
+    local_books: &mut HashMap<String, LocalBook>,
+    watchdog: &mut Watchdog,
 ) -> Result<(), HandleError> {
     let stream = serde_json::from_str::<PublicStreamMsg>(&text)?;
     match stream {
         PublicStreamMsg::Op(resp) => {
             info!(?resp, "Op");
+            if resp.op == "pong" {
+                watchdog.on_pong();
+            }
         }
         PublicStreamMsg::Topic(stream) => {
             if stream.topic.starts_with("orderbook") {
                 let data: OrderBook = serde_json::from_value(stream.data)?;
-                let (bids, asks) = parse_depth(data.bids, data.asks)?;
                 let asset_info = assets.get(&data.symbol).ok_or(HandleError::AssetNotFound)?;
-                ev_tx
-                    .send(LiveEvent::Depth(Depth {
-                        asset_no: asset_info.asset_no,
-                        exch_ts: stream.cts.unwrap() * 1_000_000,
-                        local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
-                        bids,
-                        asks,
-                    }))
-                    .unwrap();
+                let (bids, asks) = parse_depth(data.bids, data.asks)?;
+
+                let merged = if stream.r#type == "snapshot" {
+                    let book = local_books.entry(data.symbol.clone()).or_default();
+                    book.apply_snapshot(data.u, &bids, &asks);
+                    Some(book.top_n(DEPTH_LIMIT))
+                } else {
+                    match local_books.get_mut(&data.symbol) {
+                        Some(book) if book.apply_delta(data.u, &bids, &asks) => {
+                            Some(book.top_n(DEPTH_LIMIT))
+                        }
+                        _ => None,
+                    }
+                };
+
+                match merged {
+                    Some((bids, asks)) => {
+                        ev_tx
+                            .send(LiveEvent::Depth(Depth {
+                                asset_no: asset_info.asset_no,
+                                exch_ts: stream.cts.unwrap() * 1_000_000,
+                                local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                bids,
+                                asks,
+                            }))
+                            .await;
+                    }
+                    None => {
+                        warn!(
+                            symbol = %data.symbol,
+                            u = data.u,
+                            "Orderbook sequence gap detected; resubscribing for a fresh snapshot."
+                        );
+                        local_books.remove(&data.symbol);
+                        let op = Op {
+                            req_id: "resubscribe".to_string(),
+                            op: "subscribe".to_string(),
+                            args: vec![stream.topic],
+                        };
+                        let s = serde_json::to_string(&op).unwrap();
+                        write.send(Message::Text(s)).await?;
+                    }
+                }
             } else if stream.topic.starts_with("publicTrade") {
                 let data: Vec<msg::Trade> = serde_json::from_value(stream.data)?;
                 for item in data {
@@ -103,7 +478,7 @@ async fn handle_public_stream(
                             price: item.trade_price,
                             qty: item.trade_size,
                         }))
-                        .unwrap();
+                        .await;
                 }
             }
         }
@@ -113,16 +488,20 @@ async fn handle_public_stream(
 
 pub async fn connect_public(
     url: &str,
-    ev_tx: Sender<LiveEvent>,
+    ev_tx: EventTx,
     assets: HashMap<String, Asset>,
     topics: Vec<String>,
+    shutdown: CancellationToken,
 ) -> Result<(), HandleError> {
     let mut request = url.into_client_request()?;
     let _ = request.headers_mut();
 
     let (ws_stream, _) = connect_async(request).await?;
     let (mut write, mut read) = ws_stream.split();
-    let mut interval = time::interval(Duration::from_secs(15));
+    let ping_interval = Duration::from_secs(15);
+    let mut interval = time::interval(ping_interval);
+    let mut watchdog = Watchdog::new(ping_interval);
+    let mut local_books: HashMap<String, LocalBook> = HashMap::new();
 
     let mut args = Vec::new();
     for topic in topics {
@@ -136,14 +515,39 @@ pub async fn connect_public(
     let op = Op {
         req_id: "subscribe".to_string(),
         op: "subscribe".to_string(),
-        args,
+        args: args.clone(),
     };
     let s = serde_json::to_string(&op).unwrap();
     write.send(Message::Text(s)).await?;
+    info!(?args, "Subscribed to the public stream.");
 
     loop {
         select! {
+            _ = shutdown.cancelled() => {
+                let op = Op {
+                    req_id: "unsubscribe".to_string(),
+                    op: "unsubscribe".to_string(),
+                    args: args.clone(),
+                };
+                let s = serde_json::to_string(&op).unwrap();
+                let _ = write.send(Message::Text(s)).await;
+                let _ = write.send(Message::Close(None)).await;
+                info!("Shutdown requested; closed the public stream.");
+                return Ok(());
+            }
             _ = interval.tick() => {
+                if ev_tx.is_closed() {
+                    info!("Event receiver dropped; shutting down the public stream.");
+                    return Ok(());
+                }
+                if watchdog.is_stale() {
+                    warn!(
+                        since_last_message = ?watchdog.last_message_at.elapsed(),
+                        since_last_pong = ?watchdog.last_pong_at.map(|t| t.elapsed()),
+                        "Public stream looks dead; no traffic within the staleness window."
+                    );
+                    return Err(HandleError::from(WsError::ConnectionClosed));
+                }
                 let op = Op {
                     req_id: "ping".to_string(),
                     op: "ping".to_string(),
@@ -155,39 +559,101 @@ pub async fn connect_public(
             message = read.next() => {
                 match message {
                     Some(Ok(Message::Text(text))) => {
-                        if let Err(error) = handle_public_stream(&text, &ev_tx, &assets).await {
+                        watchdog.on_message();
+                        if let Err(error) = handle_public_stream(
+                            &text,
+                            &mut write,
+                            &ev_tx,
+                            &assets,
+                            &mut local_books,
+                            &mut watchdog,
+                        )
+                        .await
+                        {
                             error!(?error, %text, "Couldn't handle PublicStreamMsg.");
                         }
                     }
-                    Some(Ok(Message::Binary(_))) => {}
+                    Some(Ok(Message::Binary(_))) => {
+                        watchdog.on_message();
+                    }
                     Some(Ok(Message::Ping(_))) => {
+                        watchdog.on_message();
                         write.send(Message::Pong(Vec::new())).await?;
                     }
-                    Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Pong(_))) => {
+                        watchdog.on_pong();
+                    }
                     Some(Ok(Message::Close(close_frame))) => {
                         info!(?close_frame, "close");
-                        break;
+                        return Err(HandleError::from(WsError::ConnectionClosed));
+                    }
+                    Some(Ok(Message::Frame(_))) => {
+                        watchdog.on_message();
                     }
-                    Some(Ok(Message::Frame(_))) => {}
                     Some(Err(e)) => {
                         return Err(HandleError::from(e));
                     }
                     None => {
-                        break;
+                        return Err(HandleError::from(WsError::ConnectionClosed));
                     }
                 }
             }
         }
     }
-    Ok(())
+}
+
+/// Runs [`connect_public`] under a reconnection supervisor: whenever the
+/// connection drops, it emits a [`LiveEvent::Error`] with
+/// `ErrorKind::ConnectionInterrupted`, waits a jittered exponential backoff,
+/// and reconnects, which re-subscribes to `topics` from scratch. Returns once
+/// `shutdown` is cancelled or the event receiver has been dropped.
+pub async fn run_public_with_reconnect(
+    url: &str,
+    ev_tx: EventTx,
+    assets: HashMap<String, Asset>,
+    topics: Vec<String>,
+    shutdown: CancellationToken,
+) -> Result<(), HandleError> {
+    let mut backoff = Backoff::new();
+    loop {
+        backoff.mark_attempt_started();
+        match connect_public(
+            url,
+            ev_tx.clone(),
+            assets.clone(),
+            topics.clone(),
+            shutdown.clone(),
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(error) => {
+                warn!(?error, "Public stream disconnected.");
+                if !ev_tx
+                    .send(LiveEvent::Error(Error::with(
+                        ErrorKind::ConnectionInterrupted,
+                        error,
+                    )))
+                    .await
+                {
+                    return Ok(());
+                }
+            }
+        }
+        select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = backoff.wait() => {}
+        }
+    }
 }
 
 async fn handle_private_stream(
     text: &str,
     write: &mut SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
     assets: &HashMap<String, Asset>,
-    ev_tx: &Sender<LiveEvent>,
+    ev_tx: &EventTx,
     order_man: &WrappedOrderManager,
+    watchdog: &mut Watchdog,
 ) -> Result<(), HandleError> {
     let stream = serde_json::from_str::<PrivateStreamMsg>(&text)?;
     match stream {
@@ -198,13 +664,16 @@ async fn handle_private_stream(
                     let op = Op {
                         req_id: "3".to_string(),
                         op: "subscribe".to_string(),
-                        args: vec!["position".to_string(), "execution.fast".to_string()],
+                        args: PRIVATE_TOPICS.iter().map(|s| s.to_string()).collect(),
                     };
                     let s = serde_json::to_string(&op).unwrap();
                     write.send(Message::Text(s)).await?;
+                    info!("Authenticated and subscribed to the private stream.");
                 } else {
                     // return Err(Error::)
                 }
+            } else if resp.op == "pong" {
+                watchdog.on_pong();
             }
         }
         PrivateStreamMsg::Topic(PrivateStreamTopicMsg::Position(data)) => {
@@ -217,58 +686,70 @@ async fn handle_private_stream(
                         symbol: item.symbol,
                         qty: item.position_value,
                     }))
-                    .unwrap();
+                    .await;
             }
         }
         PrivateStreamMsg::Topic(PrivateStreamTopicMsg::FastExecution(data)) => {
             info!(?data, "FastExecution");
-            let mut order_man_ = order_man.lock().unwrap();
-            for item in &data.data {
-                match order_man_.update_execution(&item) {
-                    Ok((asset_no, order)) => {
-                        ev_tx
-                            .send(LiveEvent::Order(OrderResponse { asset_no, order }))
-                            .unwrap();
-                    }
-                    Err(error) => {
-                        error!(?error, ?data, "Couldn't update the execution data");
+            // Collected up front so the order manager's lock isn't held
+            // across the `await` below, since `send` can suspend on backpressure.
+            let mut responses = Vec::new();
+            {
+                let mut order_man_ = order_man.lock().unwrap();
+                for item in &data.data {
+                    match order_man_.update_execution(&item) {
+                        Ok((asset_no, order)) => responses.push(OrderResponse { asset_no, order }),
+                        Err(error) => {
+                            error!(?error, ?data, "Couldn't update the execution data");
+                        }
                     }
                 }
             }
+            for response in responses {
+                ev_tx.send(LiveEvent::Order(response)).await;
+            }
         }
         PrivateStreamMsg::Topic(PrivateStreamTopicMsg::Order(data)) => {
-            for item in &data.data {
+            let mut responses = Vec::new();
+            {
                 let mut order_man_ = order_man.lock().unwrap();
-                match order_man_.update_order(&item) {
-                    Ok((asset_no, order)) => {
-                        ev_tx
-                            .send(LiveEvent::Order(OrderResponse { asset_no, order }))
-                            .unwrap();
-                    }
-                    Err(error) => {
-                        error!(?error, ?data, "Couldn't update the execution data");
+                for item in &data.data {
+                    match order_man_.update_order(&item) {
+                        Ok((asset_no, order)) => responses.push(OrderResponse { asset_no, order }),
+                        Err(error) => {
+                            error!(?error, ?data, "Couldn't update the execution data");
+                        }
                     }
                 }
             }
+            for response in responses {
+                ev_tx.send(LiveEvent::Order(response)).await;
+            }
         }
     }
     Ok(())
 }
 
+/// Topics subscribed to once authentication on the private stream succeeds.
+const PRIVATE_TOPICS: [&str; 2] = ["position", "execution.fast"];
+
 pub async fn connect_private(
     url: &str,
     api_key: &str,
     secret: &str,
-    ev_tx: Sender<LiveEvent>,
+    ev_tx: EventTx,
     assets: HashMap<String, Asset>,
     order_man: WrappedOrderManager,
+    shutdown: CancellationToken,
 ) -> Result<(), HandleError> {
     let mut request = url.into_client_request()?;
     let _ = request.headers_mut();
 
     let (ws_stream, _) = connect_async(request).await?;
     let (mut write, mut read) = ws_stream.split();
-    let mut interval = time::interval(Duration::from_secs(10));
+    let ping_interval = Duration::from_secs(10);
+    let mut interval = time::interval(ping_interval);
+    let mut watchdog = Watchdog::new(ping_interval);
 
     let expires = Utc::now().timestamp_millis() + 5000;
     let signature = sign_hmac_sha256(secret, &format!("GET/realtime{expires}"));
@@ -283,7 +764,31 @@ pub async fn connect_private(
 
     loop {
         select! {
+            _ = shutdown.cancelled() => {
+                let op = Op {
+                    req_id: "unsubscribe".to_string(),
+                    op: "unsubscribe".to_string(),
+                    args: PRIVATE_TOPICS.iter().map(|s| s.to_string()).collect(),
+                };
+                let s = serde_json::to_string(&op).unwrap();
+                let _ = write.send(Message::Text(s)).await;
+                let _ = write.send(Message::Close(None)).await;
+                info!("Shutdown requested; closed the private stream.");
+                return Ok(());
+            }
             _ = interval.tick() => {
+                if ev_tx.is_closed() {
+                    info!("Event receiver dropped; shutting down the private stream.");
+                    return Ok(());
+                }
+                if watchdog.is_stale() {
+                    warn!(
+                        since_last_message = ?watchdog.last_message_at.elapsed(),
+                        since_last_pong = ?watchdog.last_pong_at.map(|t| t.elapsed()),
+                        "Private stream looks dead; no traffic within the staleness window."
+                    );
+                    return Err(HandleError::from(WsError::ConnectionClosed));
+                }
                 let op = Op {
                     req_id: "ping".to_string(),
                     op: "ping".to_string(),
@@ -295,12 +800,14 @@ pub async fn connect_private(
             message = read.next() => {
                 match message {
                     Some(Ok(Message::Text(text))) => {
+                        watchdog.on_message();
                         match handle_private_stream(
                             &text,
                             &mut write,
                             &assets,
                             &ev_tx,
-                            &order_man
+                            &order_man,
+                            &mut watchdog,
                         ).await {
                             Ok(_) => {}
                             Err(HandleError::PrefixUnmatched) => {
@@ -311,43 +818,101 @@ pub async fn connect_private(
                             }
                         }
                     }
-                    Some(Ok(Message::Binary(_))) => {}
+                    Some(Ok(Message::Binary(_))) => {
+                        watchdog.on_message();
+                    }
                     Some(Ok(Message::Ping(_))) => {
+                        watchdog.on_message();
                         write.send(Message::Pong(Vec::new())).await?;
                     }
-                    Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Pong(_))) => {
+                        watchdog.on_pong();
+                    }
                     Some(Ok(Message::Close(close_frame))) => {
                         info!(?close_frame, "close");
-                        break;
+                        return Err(HandleError::from(WsError::ConnectionClosed));
+                    }
+                    Some(Ok(Message::Frame(_))) => {
+                        watchdog.on_message();
                     }
-                    Some(Ok(Message::Frame(_))) => {}
                     Some(Err(e)) => {
                         return Err(HandleError::from(e));
                     }
                     None => {
-                        break;
+                        return Err(HandleError::from(WsError::ConnectionClosed));
                     }
                 }
             }
         }
     }
-    Ok(())
+}
+
+/// Runs [`connect_private`] under a reconnection supervisor, the private
+/// counterpart to [`run_public_with_reconnect`]: on disconnect it emits
+/// `ErrorKind::ConnectionInterrupted`, backs off, then reconnects and
+/// re-authenticates, which re-subscribes to `position` and
+/// `execution.fast`.
+pub async fn run_private_with_reconnect(
+    url: &str,
+    api_key: &str,
+    secret: &str,
+    ev_tx: EventTx,
+    assets: HashMap<String, Asset>,
+    order_man: WrappedOrderManager,
+    shutdown: CancellationToken,
+) -> Result<(), HandleError> {
+    let mut backoff = Backoff::new();
+    loop {
+        backoff.mark_attempt_started();
+        match connect_private(
+            url,
+            api_key,
+            secret,
+            ev_tx.clone(),
+            assets.clone(),
+            order_man.clone(),
+            shutdown.clone(),
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(error) => {
+                warn!(?error, "Private stream disconnected.");
+                if !ev_tx
+                    .send(LiveEvent::Error(Error::with(
+                        ErrorKind::ConnectionInterrupted,
+                        error,
+                    )))
+                    .await
+                {
+                    return Ok(());
+                }
+            }
+        }
+        select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = backoff.wait() => {}
+        }
+    }
 }
 
 pub async fn connect_trade(
     url: &str,
     api_key: &str,
     secret: &str,
-    ev_tx: Sender<LiveEvent>,
+    ev_tx: EventTx,
     order_rx: &mut UnboundedReceiver<BybitOrderReq>,
     order_man: WrappedOrderManager,
+    shutdown: CancellationToken,
 ) -> Result<(), HandleError> {
     let mut request = url.into_client_request()?;
     let _ = request.headers_mut();
 
     let (ws_stream, _) = connect_async(request).await?;
     let (mut write, mut read) = ws_stream.split();
-    let mut interval = time::interval(Duration::from_secs(60));
+    let ping_interval = Duration::from_secs(60);
+    let mut interval = time::interval(ping_interval);
+    let mut watchdog = Watchdog::new(ping_interval);
 
     let expires = Utc::now().timestamp_millis() + 5000;
     let signature = sign_hmac_sha256(secret, &format!("GET/realtime{expires}"));
@@ -363,7 +928,24 @@ pub async fn connect_trade(
 
     loop {
         select! {
+            _ = shutdown.cancelled() => {
+                let _ = write.send(Message::Close(None)).await;
+                info!("Shutdown requested; closed the trade stream.");
+                return Ok(());
+            }
             _ = interval.tick() => {
+                if ev_tx.is_closed() {
+                    info!("Event receiver dropped; shutting down the trade stream.");
+                    return Ok(());
+                }
+                if watchdog.is_stale() {
+                    warn!(
+                        since_last_message = ?watchdog.last_message_at.elapsed(),
+                        since_last_pong = ?watchdog.last_pong_at.map(|t| t.elapsed()),
+                        "Trade stream looks dead; no traffic within the staleness window."
+                    );
+                    return Err(HandleError::from(WsError::ConnectionClosed));
+                }
                 let op = Op {
                     req_id: "ping".to_string(),
                     op: "ping".to_string(),
@@ -396,17 +978,21 @@ pub async fn connect_trade(
                         write.send(Message::Text(s)).await?;
                     }
                     None => {
-                        break;
+                        // The order channel's sender was dropped, meaning the caller is
+                        // shutting this connector down; this is not a connection error.
+                        return Ok(());
                     }
                 }
             }
             message = read.next() => {
                 match message {
                     Some(Ok(Message::Text(text))) => {
+                        watchdog.on_message();
                         match handle_trade_stream(
                             &text,
                             &ev_tx,
-                            &order_man
+                            &order_man,
+                            &mut watchdog,
                         ).await {
                             Ok(_) => {}
                             Err(error) => {
@@ -414,48 +1000,108 @@ pub async fn connect_trade(
                             }
                         };
                     }
-                    Some(Ok(Message::Binary(_))) => {}
+                    Some(Ok(Message::Binary(_))) => {
+                        watchdog.on_message();
+                    }
                     Some(Ok(Message::Ping(_))) => {
+                        watchdog.on_message();
                         write.send(Message::Pong(Vec::new())).await?;
                     }
-                    Some(Ok(Message::Pong(_))) => {}
+                    Some(Ok(Message::Pong(_))) => {
+                        watchdog.on_pong();
+                    }
                     Some(Ok(Message::Close(close_frame))) => {
                         info!(?close_frame, "close");
-                        break;
+                        return Err(HandleError::from(WsError::ConnectionClosed));
+                    }
+                    Some(Ok(Message::Frame(_))) => {
+                        watchdog.on_message();
                     }
-                    Some(Ok(Message::Frame(_))) => {}
                     Some(Err(e)) => {
                         return Err(HandleError::from(e));
                     }
                     None => {
-                        break;
+                        return Err(HandleError::from(WsError::ConnectionClosed));
                     }
                 }
             }
         }
     }
-    Ok(())
+}
+
+/// Runs [`connect_trade`] under a reconnection supervisor, the trade-stream
+/// counterpart to [`run_public_with_reconnect`] and
+/// [`run_private_with_reconnect`]. `order_rx` is kept alive across
+/// reconnects so order requests submitted during a drop are sent once the
+/// trade stream has reconnected and sent its auth request; the auth reply is
+/// not awaited before they go out.
+pub async fn run_trade_with_reconnect(
+    url: &str,
+    api_key: &str,
+    secret: &str,
+    ev_tx: EventTx,
+    mut order_rx: UnboundedReceiver<BybitOrderReq>,
+    order_man: WrappedOrderManager,
+    shutdown: CancellationToken,
+) -> Result<(), HandleError> {
+    let mut backoff = Backoff::new();
+    loop {
+        backoff.mark_attempt_started();
+        match connect_trade(
+            url,
+            api_key,
+            secret,
+            ev_tx.clone(),
+            &mut order_rx,
+            order_man.clone(),
+            shutdown.clone(),
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(error) => {
+                warn!(?error, "Trade stream disconnected.");
+                if !ev_tx
+                    .send(LiveEvent::Error(Error::with(
+                        ErrorKind::ConnectionInterrupted,
+                        error,
+                    )))
+                    .await
+                {
+                    return Ok(());
+                }
+            }
+        }
+        select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = backoff.wait() => {}
+        }
+    }
 }
 
 async fn handle_trade_stream(
     text: &str,
-    ev_tx: &Sender<LiveEvent>,
+    ev_tx: &EventTx,
     order_man: &WrappedOrderManager,
+    watchdog: &mut Watchdog,
 ) -> Result<(), anyhow::Error> {
     let stream = serde_json::from_str::<TradeStreamMsg>(text)?;
-    if stream.op == "auth" {
+    if stream.op == "pong" {
+        watchdog.on_pong();
+    } else if stream.op == "auth" {
         if stream.ret_code != 0 {
             ev_tx
                 .send(LiveEvent::Error(Error::with(
                     ErrorKind::CriticalConnectionError,
                     BybitError::AuthError(stream.ret_code, stream.ret_msg.clone()),
                 )))
-                .unwrap();
+                .await;
             return Err(anyhow::Error::from(BybitError::AuthError(
                 stream.ret_code,
                 stream.ret_msg,
             )));
         }
+        info!("Authenticated with the trade stream.");
     } else if stream.op == "order.create" {
         let req_id = stream.req_id.ok_or(HandleError::ReqIdNotExist)?;
         if stream.ret_code != 0 {
@@ -466,17 +1112,17 @@ async fn handle_trade_stream(
             10016: 1. internal server error; 2. Service is restarting
             10019: ws trade service is restarting, do not accept new request, but the request in the process is not affected. You can build new connection to be routed to normal service
              */
-            let mut order_man_ = order_man.lock().unwrap();
-            let (asset_no, order) = order_man_.update_submit_fail(&req_id)?;
-            ev_tx
-                .send(LiveEvent::Order(OrderResponse { asset_no, order }))
-                .unwrap();
+            let (asset_no, order) = {
+                let mut order_man_ = order_man.lock().unwrap();
+                order_man_.update_submit_fail(&req_id)?
+            };
+            ev_tx.send(LiveEvent::Order(OrderResponse { asset_no, order })).await;
             ev_tx
                 .send(LiveEvent::Error(Error::with(
                     ErrorKind::OrderError,
                     BybitError::OrderError(stream.ret_code, stream.ret_msg.clone()),
                 )))
-                .unwrap();
+                .await;
         }
     } else if stream.op == "order.cancel" {
         let req_id = stream.req_id.ok_or(HandleError::ReqIdNotExist)?;
@@ -488,20 +1134,218 @@ async fn handle_trade_stream(
             10016: 1. internal server error; 2. Service is restarting
             10019: ws trade service is restarting, do not accept new request, but the request in the process is not affected. You can build new connection to be routed to normal service
              */
-            let mut order_man_ = order_man.lock().unwrap();
-            let (asset_no, order) = order_man_.update_cancel_fail(&req_id)?;
-            ev_tx
-                .send(LiveEvent::Order(OrderResponse { asset_no, order }))
-                .unwrap();
+            let (asset_no, order) = {
+                let mut order_man_ = order_man.lock().unwrap();
+                order_man_.update_cancel_fail(&req_id)?
+            };
+            ev_tx.send(LiveEvent::Order(OrderResponse { asset_no, order })).await;
             ev_tx
                 .send(LiveEvent::Error(Error::with(
                     ErrorKind::OrderError,
                     BybitError::OrderError(stream.ret_code, stream.ret_msg.clone()),
                 )))
-                .unwrap();
+                .await;
         }
     } else {
         info!(?stream, "trade stream");
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_book_merges_a_delta_on_top_of_the_snapshot() {
+        let mut book = LocalBook::default();
+        book.apply_snapshot(1, &[(100.0, 1.0), (99.0, 2.0)], &[(101.0, 1.0), (102.0, 2.0)]);
+        assert!(book.apply_delta(2, &[(100.0, 1.5)], &[]));
+
+        let (bids, asks) = book.top_n(10);
+        assert_eq!(bids, vec![(100.0, 1.5), (99.0, 2.0)]);
+        assert_eq!(asks, vec![(101.0, 1.0), (102.0, 2.0)]);
+    }
+
+    #[test]
+    fn local_book_delta_qty_zero_removes_the_level() {
+        let mut book = LocalBook::default();
+        book.apply_snapshot(1, &[], &[(101.0, 1.0), (102.0, 2.0)]);
+        assert!(book.apply_delta(2, &[], &[(101.0, 0.0)]));
+
+        let (_, asks) = book.top_n(10);
+        assert_eq!(asks, vec![(102.0, 2.0)]);
+    }
+
+    #[test]
+    fn local_book_delta_sequence_gap_is_rejected() {
+        let mut book = LocalBook::default();
+        book.apply_snapshot(1, &[(100.0, 1.0)], &[]);
+        assert!(!book.apply_delta(3, &[(100.0, 2.0)], &[]));
+
+        let mut fresh = LocalBook::default();
+        assert!(!fresh.apply_delta(1, &[(100.0, 1.0)], &[]));
+    }
+
+    #[tokio::test]
+    async fn backoff_doubles_up_to_the_cap() {
+        let mut backoff = Backoff {
+            current: Backoff::MAX / 2,
+            connected_at: None,
+        };
+        backoff.wait().await;
+        assert_eq!(backoff.current, Backoff::MAX);
+        backoff.wait().await;
+        assert_eq!(backoff.current, Backoff::MAX);
+    }
+
+    #[tokio::test]
+    async fn backoff_resets_after_a_stable_connection() {
+        let mut backoff = Backoff {
+            current: Backoff::MAX,
+            connected_at: Instant::now().checked_sub(Backoff::STABLE_AFTER + Duration::from_secs(1)),
+        };
+        backoff.wait().await;
+        assert_eq!(backoff.current, Backoff::INITIAL * 2);
+    }
+
+    #[tokio::test]
+    async fn event_pipe_conflates_depth_updates_for_the_same_asset() {
+        let (tx, rx) = event_channel();
+        assert!(
+            tx.send(LiveEvent::Depth(Depth {
+                asset_no: 0,
+                exch_ts: 1,
+                local_ts: 1,
+                bids: vec![(100.0, 1.0)],
+                asks: vec![],
+            }))
+            .await
+        );
+        assert!(
+            tx.send(LiveEvent::Depth(Depth {
+                asset_no: 0,
+                exch_ts: 2,
+                local_ts: 2,
+                bids: vec![(101.0, 2.0)],
+                asks: vec![],
+            }))
+            .await
+        );
+
+        match rx.recv() {
+            Some(LiveEvent::Depth(depth)) => assert_eq!(depth.exch_ts, 2),
+            other => panic!("expected a single conflated Depth event, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn event_pipe_preserves_send_order_for_position_and_error_events() {
+        let (tx, rx) = event_channel();
+        assert!(
+            tx.send(LiveEvent::Position(Position {
+                asset_no: 1,
+                symbol: "BTCUSDT".to_string(),
+                qty: 1.0,
+            }))
+            .await
+        );
+        assert!(
+            tx.send(LiveEvent::Error(Error::with(
+                ErrorKind::ConnectionInterrupted,
+                BybitError::AuthError(1, "boom".to_string()),
+            )))
+            .await
+        );
+
+        match rx.recv() {
+            Some(LiveEvent::Position(position)) => assert_eq!(position.asset_no, 1),
+            other => panic!("expected Position first, got {other:?}"),
+        }
+        match rx.recv() {
+            Some(LiveEvent::Error(_)) => {}
+            other => panic!("expected Error second, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn event_pipe_reports_closed_once_the_receiver_is_dropped() {
+        let (tx, rx) = event_channel();
+        assert!(!tx.is_closed());
+        drop(rx);
+        assert!(tx.is_closed());
+        assert!(
+            !tx.send(LiveEvent::Position(Position {
+                asset_no: 0,
+                symbol: "BTCUSDT".to_string(),
+                qty: 0.0,
+            }))
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn event_pipe_send_unblocks_instead_of_growing_past_capacity_once_full() {
+        let (tx, rx) = event_channel();
+        for i in 0..EVENT_QUEUE_CAPACITY {
+            assert!(
+                tx.send(LiveEvent::Position(Position {
+                    asset_no: i,
+                    symbol: "BTCUSDT".to_string(),
+                    qty: 0.0,
+                }))
+                .await
+            );
+        }
+
+        let dropper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            drop(rx);
+        });
+
+        let sent = tx
+            .send(LiveEvent::Position(Position {
+                asset_no: EVENT_QUEUE_CAPACITY,
+                symbol: "BTCUSDT".to_string(),
+                qty: 0.0,
+            }))
+            .await;
+        assert!(!sent);
+        dropper.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn event_pipe_depth_updates_never_count_against_the_other_event_capacity() {
+        let (tx, rx) = event_channel();
+
+        // Thousands of distinct-asset Depth updates dwarf EVENT_QUEUE_CAPACITY, but
+        // since they're conflated per-asset and tracked separately from
+        // `other_count`, they must never block an Order/Position/Error send.
+        for asset_no in 0..(EVENT_QUEUE_CAPACITY * 4) {
+            assert!(
+                tx.send(LiveEvent::Depth(Depth {
+                    asset_no,
+                    exch_ts: asset_no as i64,
+                    local_ts: asset_no as i64,
+                    bids: vec![],
+                    asks: vec![],
+                }))
+                .await
+            );
+        }
+
+        for i in 0..EVENT_QUEUE_CAPACITY {
+            assert!(
+                tx.send(LiveEvent::Position(Position {
+                    asset_no: i,
+                    symbol: "BTCUSDT".to_string(),
+                    qty: 0.0,
+                }))
+                .await,
+                "Other-event capacity should still be free; Depth must not count against it"
+            );
+        }
+
+        drop(rx);
+    }
+}